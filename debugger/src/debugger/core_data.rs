@@ -5,9 +5,68 @@ use crate::{
     peripherals::svd_variables::SvdCache,
     DebuggerError,
 };
-use anyhow::Result;
-use probe_rs::{debug::debug_info::DebugInfo, Core};
+use anyhow::{anyhow, Result};
+use probe_rs::{debug::debug_info::DebugInfo, Core, CoreStatus};
 use probe_rs_cli_util::rtt::{self, ChannelMode, DataFormat};
+use std::ops::Range;
+
+/// The byte value painted over the unused stack region, so that [`CoreHandle::check_stack_canary`]
+/// can detect how deep the stack has been used. Chosen to match probe-run, which uses the same
+/// pattern for its stack painting.
+const STACK_CANARY_PATTERN: u8 = 0xAA;
+
+/// Linker symbol marking the initial (highest) stack pointer value, emitted by `cortex-m-rt` and
+/// compatible runtimes.
+const STACK_TOP_SYMBOL: &str = "_stack_start";
+
+/// Linker symbol marking the lowest address the stack region may grow into before it starts
+/// overlapping statically allocated RAM (`.bss`/`.data`/heap), emitted by `cortex-m-rt` and
+/// compatible runtimes.
+const STACK_BOTTOM_SYMBOL: &str = "_stack_end";
+
+/// Default number of bytes (counting down from [`STACK_TOP_SYMBOL`]) that
+/// [`CoreHandle::paint_stack_canary`] paints when the user hasn't configured
+/// `CoreConfig::stack_canary_budget`, used both as the ceiling on the common case (both linker
+/// symbols resolve) and as the fallback window when [`STACK_BOTTOM_SYMBOL`] can't be resolved.
+const STACK_CANARY_DEFAULT_BUDGET: u64 = 8 * 1024;
+
+/// Tracks the painted stack-overflow canary region for a core, modeled on probe-run's stack
+/// painting. See [`CoreHandle::paint_stack_canary`] and [`CoreHandle::check_stack_canary`].
+pub(crate) struct StackCanary {
+    pattern: u8,
+    painted_range: Range<u64>,
+}
+
+/// Clamp `resolved_stack_bottom` (from [`STACK_BOTTOM_SYMBOL`], or already defaulted to
+/// `stack_top - budget` if that symbol didn't resolve) so that the painted region is never more
+/// than `budget` bytes, regardless of how far apart the linker symbols are.
+fn clamp_stack_canary_bottom(stack_top: u64, resolved_stack_bottom: u64, budget: u64) -> u64 {
+    resolved_stack_bottom.max(stack_top.saturating_sub(budget))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_keeps_a_tight_linker_range_unchanged() {
+        assert_eq!(clamp_stack_canary_bottom(0x2000_1000, 0x2000_0800, 8 * 1024), 0x2000_0800);
+    }
+
+    #[test]
+    fn clamp_bounds_a_large_linker_range_to_the_budget() {
+        // Linker symbols 1 MiB apart, but the budget only allows 8 KiB to be painted.
+        assert_eq!(
+            clamp_stack_canary_bottom(0x2010_0000, 0x2000_0000, 8 * 1024),
+            0x2010_0000 - 8 * 1024
+        );
+    }
+
+    #[test]
+    fn clamp_is_a_no_op_when_budget_exceeds_the_range() {
+        assert_eq!(clamp_stack_canary_bottom(0x2000_1000, 0x2000_0000, 1024 * 1024), 0x2000_0000);
+    }
+}
 
 /// [CoreData] is used to cache data needed by the debugger, on a per-core basis.
 pub struct CoreData {
@@ -18,6 +77,15 @@ pub struct CoreData {
     pub(crate) stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
     pub(crate) breakpoints: Vec<session_data::ActiveBreakpoint>,
     pub(crate) rtt_connection: Option<debug_rtt::RttConnection>,
+    /// The address of the temporary "setup" breakpoint installed while we wait for the target to
+    /// reach `rtt::RttConfig::setup_on_breakpoint`, before we attach to RTT. `None` when no such
+    /// breakpoint is pending (either none was configured, or we have already attached).
+    pub(crate) rtt_setup_breakpoint: Option<u64>,
+    /// The painted stack-overflow canary region, once [`CoreHandle::paint_stack_canary`] has run.
+    pub(crate) stack_canary: Option<StackCanary>,
+    /// Set once we have painted the stack canary for this core, so that we only do it on the
+    /// first resume after flashing and not on every subsequent run.
+    pub(crate) has_painted_stack_canary: bool,
 }
 
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [SessionData].
@@ -40,7 +108,111 @@ impl<'p> CoreHandle<'p> {
             .find(|stack_frame| stack_frame.id == id)
     }
 
+    /// Paint the currently unused stack region with [`STACK_CANARY_PATTERN`], so that a later call
+    /// to [`Self::check_stack_canary`] can detect whether the stack has grown into it, modeled on
+    /// probe-run's stack painting. Should be called on the first resume after flashing, while the
+    /// stack pointer still reflects its initial (mostly unused) value.
+    ///
+    /// The painted range runs from [`STACK_BOTTOM_SYMBOL`] (the linker-provided address where the
+    /// stack region ends and static data begins) up to the lower of [`STACK_TOP_SYMBOL`] (the
+    /// linker-provided initial stack pointer) and the current stack pointer, so that we never
+    /// paint over the live call frames that got us here. If [`STACK_BOTTOM_SYMBOL`] can't be
+    /// resolved from [`CoreData::debug_info`], we fall back to `budget` bytes below the top,
+    /// rather than risk overlapping whatever comes below an unknown stack region.
+    ///
+    /// `budget` (`CoreConfig::stack_canary_budget`, from the launch configuration) is always
+    /// applied as a ceiling on top of whatever the linker symbols resolve to, not just as a
+    /// fallback: a target with a large stack can still bound the cost (and risk) of the painting
+    /// write, and the corresponding read-back in [`Self::check_stack_canary`]. Defaults to
+    /// [`STACK_CANARY_DEFAULT_BUDGET`] when unset. Painting is skipped entirely if there is no
+    /// headroom between the resolved bounds.
+    pub(crate) fn paint_stack_canary(&mut self, budget: Option<u64>) -> Result<(), DebuggerError> {
+        let budget = budget.unwrap_or(STACK_CANARY_DEFAULT_BUDGET);
+
+        let stack_pointer: u64 = self
+            .core
+            .read_core_reg(self.core.registers().stack_pointer())
+            .map_err(DebuggerError::ProbeRs)?;
+
+        let stack_top = self
+            .core_data
+            .debug_info
+            .address_for_symbol(STACK_TOP_SYMBOL)
+            .unwrap_or(stack_pointer)
+            .min(stack_pointer);
+
+        let resolved_stack_bottom = self
+            .core_data
+            .debug_info
+            .address_for_symbol(STACK_BOTTOM_SYMBOL)
+            .unwrap_or(stack_top.saturating_sub(budget));
+        let stack_bottom = clamp_stack_canary_bottom(stack_top, resolved_stack_bottom, budget);
+
+        if stack_bottom >= stack_top {
+            // No headroom to paint (or the resolved bounds don't make sense); leave the canary
+            // disabled for this core rather than risk painting over live data.
+            return Ok(());
+        }
+        let painted_range = stack_bottom..stack_top;
+
+        let pattern = vec![STACK_CANARY_PATTERN; (painted_range.end - painted_range.start) as usize];
+        self.core
+            .write_8(painted_range.start, &pattern)
+            .map_err(DebuggerError::ProbeRs)?;
+
+        self.core_data.stack_canary = Some(StackCanary {
+            pattern: STACK_CANARY_PATTERN,
+            painted_range,
+        });
+
+        Ok(())
+    }
+
+    /// Read back the region painted by [`Self::paint_stack_canary`] and report the measured
+    /// maximum stack usage, as a percentage of the painted region. Returns `Ok(None)` if no
+    /// canary has been painted for this core. If the stack has used the entire painted region
+    /// (i.e. it may have overflowed into whatever comes below it), returns a
+    /// [`DebuggerError::Other`] describing the overflow instead.
+    pub(crate) fn check_stack_canary(&mut self) -> Result<Option<f32>, DebuggerError> {
+        let Some(canary) = &self.core_data.stack_canary else {
+            return Ok(None);
+        };
+        let painted_range = canary.painted_range.clone();
+        let pattern = canary.pattern;
+
+        let mut painted_memory = vec![0u8; (painted_range.end - painted_range.start) as usize];
+        self.core
+            .read_8(painted_range.start, &mut painted_memory)
+            .map_err(DebuggerError::ProbeRs)?;
+
+        // Scanning from the lowest (deepest) address upward, the first byte that no longer
+        // matches the pattern marks the high-water mark of stack usage.
+        let untouched_bytes = painted_memory
+            .iter()
+            .take_while(|&&byte| byte == pattern)
+            .count();
+        let used_bytes = painted_memory.len() - untouched_bytes;
+        let usage_percent = (used_bytes as f32 / painted_memory.len() as f32) * 100.0;
+
+        if untouched_bytes == 0 {
+            return Err(DebuggerError::Other(anyhow!(
+                "Stack overflow detected: the stack has used its entire {}-byte painted canary region",
+                painted_memory.len()
+            )));
+        }
+
+        Ok(Some(usage_percent))
+    }
+
     /// Confirm RTT initialization on the target, and use the RTT channel configurations to initialize the output windows on the DAP Client.
+    ///
+    /// If `rtt_config.setup_on_breakpoint` is configured, we defer the actual attach until the
+    /// core halts at that location: a temporary hardware breakpoint is installed on the first
+    /// call, and subsequent calls (driven by [`super::session_data::SessionData::poll_cores`])
+    /// return early until the core reports [`CoreStatus::Halted`] with its program counter at
+    /// the setup location. This avoids racing the
+    /// target's own RTT control block initialization, which would otherwise force the defmt
+    /// channel into [`ChannelMode::BlockIfFull`] before it is safe to do so and drop early frames.
     pub fn attach_to_rtt<P: ProtocolAdapter>(
         &mut self,
         debug_adapter: &mut DebugAdapter<P>,
@@ -48,12 +220,87 @@ impl<'p> CoreHandle<'p> {
         program_binary: &std::path::Path,
         rtt_config: &rtt::RttConfig,
     ) -> Result<()> {
+        if let Some(setup_location) = rtt_config.setup_on_breakpoint.as_deref() {
+            if self.core_data.rtt_setup_breakpoint.is_none() {
+                let setup_address = if let Some(hex_address) = setup_location.strip_prefix("0x") {
+                    let address = u64::from_str_radix(hex_address, 16).map_err(|_| {
+                        DebuggerError::Other(anyhow!(
+                            "Invalid RTT setup breakpoint address: `{}`",
+                            setup_location
+                        ))
+                    })?;
+                    self.set_breakpoint(
+                        address,
+                        session_data::BreakpointType::InstructionBreakpoint,
+                    )?;
+                    address
+                } else {
+                    // A symbol name rather than a raw address: resolve and track it by name, so
+                    // that `clear_breakpoints`/DAP responses can refer back to it.
+                    self.set_breakpoint_at_symbol(
+                        setup_location,
+                        rtt_config.thumb,
+                        session_data::BreakpointType::InstructionBreakpoint,
+                    )?
+                };
+                self.core_data.rtt_setup_breakpoint = Some(setup_address);
+                log::debug!(
+                    "Waiting for the target to reach the RTT setup breakpoint at {:#010x} before attaching to RTT.",
+                    setup_address
+                );
+            }
+
+            if !matches!(
+                self.core.status().map_err(DebuggerError::ProbeRs)?,
+                CoreStatus::Halted(_)
+            ) {
+                // Still waiting for the target to reach the setup breakpoint.
+                return Ok(());
+            }
+
+            // Halted somewhere else (e.g. at a user breakpoint): keep waiting for the setup location.
+            let program_counter: u64 = self
+                .core
+                .read_core_reg(self.core.registers().program_counter())
+                .map_err(DebuggerError::ProbeRs)?;
+            let setup_address = self.core_data.rtt_setup_breakpoint.map(|address| address & !1);
+            if Some(program_counter & !1) != setup_address {
+                return Ok(());
+            }
+        }
+
+        // Resolve `control_block_symbol`/`scan_region_name` against this core's debug info and
+        // the already-parsed memory map before delegating to `rtt::attach_to_rtt`: only this
+        // crate has access to `CoreData::debug_info`, so a raw address/region has to be
+        // substituted in here rather than in `probe-rs-cli-util`.
+        let mut resolved_rtt_config = rtt_config.clone();
+        if resolved_rtt_config.control_block_address.is_none() {
+            if let Some(symbol) = resolved_rtt_config.control_block_symbol.as_deref() {
+                resolved_rtt_config.control_block_address =
+                    self.core_data.debug_info.address_for_symbol(symbol);
+            }
+        }
+        if resolved_rtt_config.scan_region.is_none() {
+            if let Some(region_name) = resolved_rtt_config.scan_region_name.as_deref() {
+                resolved_rtt_config.scan_region = target_memory_map.iter().find_map(|region| {
+                    match region {
+                        probe_rs::config::MemoryRegion::Ram(ram)
+                            if ram.name.as_deref() == Some(region_name) =>
+                        {
+                            Some(ram.range.clone())
+                        }
+                        _ => None,
+                    }
+                });
+            }
+        }
+
         let mut debugger_rtt_channels: Vec<debug_rtt::DebuggerRttChannel> = vec![];
         match rtt::attach_to_rtt(
             &mut self.core,
             target_memory_map,
             program_binary,
-            rtt_config,
+            &resolved_rtt_config,
         ) {
             Ok(target_rtt) => {
                 for any_channel in target_rtt.active_channels.iter() {
@@ -74,18 +321,43 @@ impl<'p> CoreHandle<'p> {
                         );
                     }
                 }
-                self.core_data.rtt_connection = Some(debug_rtt::RttConnection {
+                self.core_data.rtt_connection = Some(debug_rtt::RttConnection::new(
                     target_rtt,
                     debugger_rtt_channels,
-                });
+                    program_binary.to_path_buf(),
+                ));
+
+                // Only now that RTT is actually attached do we remove the gating breakpoint and
+                // let the core run again. If we did this unconditionally (including on the
+                // failure branch below), a single transient attach failure would resume the core
+                // past a one-shot setup location it will typically never reach again, permanently
+                // preventing RTT from attaching.
+                if let Some(setup_address) = self.core_data.rtt_setup_breakpoint.take() {
+                    self.clear_breakpoint(setup_address)?;
+                    self.core.run().map_err(DebuggerError::ProbeRs)?;
+                }
             }
             Err(_error) => {
+                // Leave the core halted at the setup breakpoint and retry on the next poll.
                 log::warn!("Failed to initalize RTT. Will try again on the next request... ");
             }
         };
+
         Ok(())
     }
 
+    /// Resolve `symbol` (e.g. `main`, `rust_begin_unwind`) to an address via
+    /// [`CoreData::debug_info`], clearing the thumb bit (LSB) when `thumb` is set so a hardware
+    /// breakpoint installed there lands on the real instruction.
+    fn resolve_symbol_address(&self, symbol: &str, thumb: bool) -> Result<u64> {
+        let address = self
+            .core_data
+            .debug_info
+            .address_for_symbol(symbol)
+            .ok_or_else(|| anyhow!("Could not resolve symbol `{}`", symbol))?;
+        Ok(if thumb { address & !1 } else { address })
+    }
+
     /// Set a single breakpoint in target configuration as well as [`CoreHandle::breakpoints`]
     pub(crate) fn set_breakpoint(
         &mut self,
@@ -100,10 +372,40 @@ impl<'p> CoreHandle<'p> {
             .push(session_data::ActiveBreakpoint {
                 breakpoint_type,
                 breakpoint_address: address,
+                source_symbol: None,
             });
         Ok(())
     }
 
+    /// Set a breakpoint at the address of `symbol` (e.g. `main`, `rust_begin_unwind`, `HardFault`),
+    /// resolved via [`CoreData::debug_info`] instead of requiring the caller to pre-resolve it.
+    /// Clears the thumb bit (LSB) when `thumb` is set, so the hardware breakpoint lands on the
+    /// real instruction for thumb function entry points. The symbol is recorded on the resulting
+    /// [`session_data::ActiveBreakpoint`]; [`Self::clear_breakpoint`] and
+    /// [`Self::clear_breakpoints`] still match breakpoints by address and type only, not by
+    /// name. This is what enables config-driven "break on panic handler / HardFault" breakpoints.
+    pub(crate) fn set_breakpoint_at_symbol(
+        &mut self,
+        symbol: &str,
+        thumb: bool,
+        breakpoint_type: session_data::BreakpointType,
+    ) -> Result<u64, DebuggerError> {
+        let address = self
+            .resolve_symbol_address(symbol, thumb)
+            .map_err(DebuggerError::Other)?;
+        self.core
+            .set_hw_breakpoint(address)
+            .map_err(DebuggerError::ProbeRs)?;
+        self.core_data
+            .breakpoints
+            .push(session_data::ActiveBreakpoint {
+                breakpoint_type,
+                breakpoint_address: address,
+                source_symbol: Some(symbol.to_string()),
+            });
+        Ok(address)
+    }
+
     /// Clear a single breakpoint from target configuration as well as [`CoreHandle::breakpoints`]
     pub(crate) fn clear_breakpoint(&mut self, address: u64) -> Result<()> {
         self.core
@@ -0,0 +1,196 @@
+use crate::debug_adapter::{dap_adapter::DebugAdapter, dap_types::Source, protocol::ProtocolAdapter};
+use anyhow::{anyhow, Result};
+use probe_rs::Core;
+use probe_rs_cli_util::rtt::DataFormat;
+use std::path::PathBuf;
+
+/// Metadata the debugger keeps about an RTT up-channel, in addition to what
+/// `probe_rs_cli_util::rtt` already tracks for it.
+pub(crate) struct DebuggerRttChannel {
+    pub(crate) channel_number: usize,
+    /// This value will eventually be set to true by a VSCode client request "rttWindowOpened"
+    pub(crate) has_client_window: bool,
+}
+
+/// Lazily-loaded `defmt` decoding state for a core's RTT connection, populated the first time a
+/// [`DataFormat::Defmt`] channel produces data. Kept around (rather than reloaded per poll) so
+/// that frames split across polls are decoded correctly.
+struct DefmtState {
+    // SAFETY (see `RttConnection::defmt_state`): this borrows from `table` below, with its
+    // lifetime extended to `'static`. Never replace `table` without also recreating this. Field
+    // order matters here: struct fields drop top-to-bottom, so `stream_decoder` must be declared
+    // (and therefore dropped) *before* `table`, or its drop glue would run against a dangling
+    // reference into an already-freed `table`.
+    stream_decoder: Box<dyn defmt_decoder::StreamDecoder>,
+    // Boxed so its address (and therefore the borrow `stream_decoder` holds into it) stays
+    // stable even if `DefmtState` itself is moved.
+    table: Box<defmt_decoder::Table>,
+    locations: Option<defmt_decoder::Locations>,
+}
+
+impl DefmtState {
+    /// Create a fresh stream decoder from `table`, with nothing buffered from any previous
+    /// decoder — used both for initial construction and to recover from
+    /// [`defmt_decoder::DecodeError::Malformed`].
+    fn new_stream_decoder(table: &defmt_decoder::Table) -> Box<dyn defmt_decoder::StreamDecoder> {
+        // SAFETY: `new_stream_decoder` borrows from `table` for as long as the returned decoder
+        // lives. `table` is heap-allocated and stored alongside the decoder in `DefmtState` for
+        // its entire lifetime, so extending the borrow to `'static` here is sound as long as
+        // nothing else observes it past `DefmtState`'s own lifetime.
+        unsafe {
+            std::mem::transmute::<
+                Box<dyn defmt_decoder::StreamDecoder + '_>,
+                Box<dyn defmt_decoder::StreamDecoder>,
+            >(table.new_stream_decoder())
+        }
+    }
+}
+
+/// A live RTT connection for a single core: the lower-level `probe-rs-cli-util` RTT scan result,
+/// plus the state the debugger needs to stream channel data to the DAP client.
+pub(crate) struct RttConnection {
+    pub(crate) target_rtt: probe_rs_cli_util::rtt::RttActiveTarget,
+    pub(crate) debugger_rtt_channels: Vec<DebuggerRttChannel>,
+    /// The ELF that `defmt` tables and source locations are loaded from, on demand.
+    pub(crate) program_binary: PathBuf,
+    defmt_state: Option<DefmtState>,
+}
+
+impl RttConnection {
+    pub(crate) fn new(
+        target_rtt: probe_rs_cli_util::rtt::RttActiveTarget,
+        debugger_rtt_channels: Vec<DebuggerRttChannel>,
+        program_binary: PathBuf,
+    ) -> Self {
+        Self {
+            target_rtt,
+            debugger_rtt_channels,
+            program_binary,
+            defmt_state: None,
+        }
+    }
+
+    /// Poll every active up-channel for new data and forward it to the DAP client: raw bytes for
+    /// plain-text/binary channels, and decoded `defmt` frames (with source location and log
+    /// level, when available) for [`DataFormat::Defmt`] channels. Returns `true` if any channel
+    /// produced data, so that the caller can skip its inter-poll delay and keep draining.
+    pub(crate) fn process_rtt_data<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+        core: &mut Core,
+    ) -> bool {
+        let mut saw_new_data = false;
+        let mut read_buffer = [0u8; 1024];
+
+        for any_channel in self.target_rtt.active_channels.iter_mut() {
+            let Some(up_channel) = &mut any_channel.up_channel else {
+                continue;
+            };
+            let channel_number = up_channel.number();
+
+            let read_count = match up_channel.read(core, read_buffer.as_mut()) {
+                Ok(0) => continue,
+                Ok(read_count) => read_count,
+                Err(error) => {
+                    log::warn!("Error reading from RTT channel {channel_number}: {error:?}");
+                    continue;
+                }
+            };
+            saw_new_data = true;
+            let incoming_bytes = &read_buffer[..read_count];
+
+            match any_channel.data_format {
+                DataFormat::Defmt => {
+                    if let Err(error) =
+                        self.process_defmt_data(debug_adapter, channel_number, incoming_bytes)
+                    {
+                        log::warn!(
+                            "Failed to decode defmt data on RTT channel {channel_number}: {error:?}"
+                        );
+                    }
+                }
+                _ => {
+                    debug_adapter.rtt_output(
+                        channel_number,
+                        String::from_utf8_lossy(incoming_bytes).to_string(),
+                    );
+                }
+            }
+        }
+
+        saw_new_data
+    }
+
+    /// Feed newly received bytes through the streaming `defmt` decoder and emit a DAP `output`
+    /// event for every complete frame it yields. The decoder retains any trailing partial frame
+    /// across calls, so bytes that arrive split across polls still decode correctly.
+    fn process_defmt_data<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+        channel_number: usize,
+        incoming_bytes: &[u8],
+    ) -> Result<()> {
+        let defmt_state = self.defmt_state()?;
+        defmt_state.stream_decoder.received(incoming_bytes);
+
+        loop {
+            let frame = match defmt_state.stream_decoder.decode() {
+                Ok(frame) => frame,
+                // Not enough bytes for a full frame yet; wait for the next poll.
+                Err(defmt_decoder::DecodeError::UnexpectedEof) => break,
+                Err(defmt_decoder::DecodeError::Malformed) => {
+                    // `StreamDecoder` makes no guarantee that it has consumed the bad bytes, so
+                    // looping here could spin forever on the poll thread instead of waiting for
+                    // more data. Recreating the decoder is the only way to guarantee we make
+                    // progress; this does mean we give up on whatever was left in its buffer.
+                    log::warn!(
+                        "Encountered a malformed defmt frame; resetting the stream decoder."
+                    );
+                    defmt_state.stream_decoder = DefmtState::new_stream_decoder(&defmt_state.table);
+                    break;
+                }
+            };
+
+            let location = defmt_state
+                .locations
+                .as_ref()
+                .and_then(|locations| locations.get(&frame.index()));
+            let source = location.map(|location| Source {
+                name: location.file.file_name().map(|name| name.to_string_lossy().into_owned()),
+                path: Some(location.file.display().to_string()),
+                ..Default::default()
+            });
+
+            debug_adapter.rtt_defmt_output(
+                channel_number,
+                frame.display(false).to_string(),
+                frame.level(),
+                source,
+                location.map(|location| location.line as i64),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn defmt_state(&mut self) -> Result<&mut DefmtState> {
+        if self.defmt_state.is_none() {
+            let elf_contents = std::fs::read(&self.program_binary)?;
+            let table = defmt_decoder::Table::parse(&elf_contents)?.ok_or_else(|| {
+                anyhow!(
+                    "No defmt data found in {:?}. Was the program built with the `defmt` feature enabled?",
+                    self.program_binary
+                )
+            })?;
+            let locations = table.get_locations(&elf_contents).ok();
+            let table = Box::new(table);
+            let stream_decoder = DefmtState::new_stream_decoder(&table);
+            self.defmt_state = Some(DefmtState {
+                table,
+                locations,
+                stream_decoder,
+            });
+        }
+        Ok(self.defmt_state.as_mut().expect("just populated above"))
+    }
+}
@@ -0,0 +1,401 @@
+use super::{
+    configuration::SessionConfig,
+    session_data::{BreakpointType, SessionData},
+};
+use crate::DebuggerError;
+use anyhow::{anyhow, Result};
+use probe_rs::{CoreStatus, HaltReason};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+/// The core index that the GDB server exposes. Matches the single-core restriction that
+/// [`SessionData::new`] currently enforces on [`SessionConfig::core_configs`].
+const GDB_CORE_INDEX: usize = 0;
+
+/// Serves the GDB Remote Serial Protocol on `port`, using `session_data` as the execution
+/// backend. This lets `gdb`, `lldb`, and editor GDB integrations drive the same [`SessionData`]/
+/// [`super::core_data::CoreHandle`] machinery that the DAP adapter uses, so a user can debug with
+/// either client without the probe-rs side needing to know which one is attached.
+pub(crate) struct GdbServer {
+    listener: TcpListener,
+}
+
+impl GdbServer {
+    pub(crate) fn new(port: u16) -> Result<Self, DebuggerError> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+        Ok(Self { listener })
+    }
+
+    /// Accept a single GDB client and serve it until it disconnects or requests a detach.
+    /// probe-rs only supports one GDB client talking to a given [`SessionData`] at a time, mirroring
+    /// the DAP adapter's single-client model.
+    pub(crate) fn run(
+        &mut self,
+        session_data: &mut SessionData,
+        session_config: &SessionConfig,
+    ) -> Result<(), DebuggerError> {
+        let (stream, _) = self
+            .listener
+            .accept()
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+        let mut connection = GdbConnection { stream };
+        connection.serve(session_data, session_config)
+    }
+}
+
+struct GdbConnection {
+    stream: TcpStream,
+}
+
+impl GdbConnection {
+    fn serve(
+        &mut self,
+        session_data: &mut SessionData,
+        session_config: &SessionConfig,
+    ) -> Result<(), DebuggerError> {
+        loop {
+            let packet = match self.read_packet()? {
+                Some(packet) => packet,
+                None => return Ok(()), // Client closed the connection.
+            };
+
+            if packet.starts_with('D') {
+                // Detach: acknowledge and stop serving this client.
+                self.write_packet("OK")?;
+                return Ok(());
+            }
+
+            let response = self.dispatch(&packet, session_data, session_config)?;
+            self.write_packet(&response)?;
+        }
+    }
+
+    /// Map a single GDB Remote Serial Protocol packet onto the equivalent [`SessionData`] /
+    /// [`super::core_data::CoreHandle`] operation.
+    fn dispatch(
+        &mut self,
+        packet: &str,
+        session_data: &mut SessionData,
+        session_config: &SessionConfig,
+    ) -> Result<String, DebuggerError> {
+        let mut core_handle = session_data.attach_core(GDB_CORE_INDEX)?;
+
+        let response = if packet == "?" {
+            Self::stop_reply(core_handle.core.status().map_err(DebuggerError::ProbeRs)?)
+        } else if packet == "g" {
+            // Read all registers, in the target-description order.
+            let register_file = core_handle.core.registers();
+            let mut encoded = String::new();
+            for register in register_file.registers() {
+                let value: u32 = core_handle
+                    .core
+                    .read_core_reg(register)
+                    .map_err(DebuggerError::ProbeRs)?;
+                encoded.push_str(&hex_encode(&value.to_le_bytes()));
+            }
+            encoded
+        } else if let Some(rest) = packet.strip_prefix('m') {
+            // mADDR,LENGTH: read target memory.
+            let (address, length) = Self::parse_address_length(rest)?;
+            let mut buffer = vec![0u8; length as usize];
+            core_handle
+                .core
+                .read_8(address, &mut buffer)
+                .map_err(DebuggerError::ProbeRs)?;
+            hex_encode(&buffer)
+        } else if let Some(rest) = packet.strip_prefix('M') {
+            // MADDR,LENGTH:DATA: write target memory.
+            let (address_length, data) = rest
+                .split_once(':')
+                .ok_or_else(|| DebuggerError::Other(anyhow!("Malformed 'M' packet")))?;
+            let (address, _length) = Self::parse_address_length(address_length)?;
+            let data = hex_decode(data)?;
+            core_handle
+                .core
+                .write_8(address, &data)
+                .map_err(DebuggerError::ProbeRs)?;
+            "OK".to_string()
+        } else if let Some(rest) = packet.strip_prefix("Z0,") {
+            // Z0,ADDR,KIND: insert a software (treated as hardware) breakpoint.
+            let address = Self::parse_breakpoint_address(rest)?;
+            core_handle.set_breakpoint(address, BreakpointType::InstructionBreakpoint)?;
+            "OK".to_string()
+        } else if let Some(rest) = packet.strip_prefix("Z1,") {
+            // Z1,ADDR,KIND: insert a hardware breakpoint.
+            let address = Self::parse_breakpoint_address(rest)?;
+            core_handle.set_breakpoint(address, BreakpointType::InstructionBreakpoint)?;
+            "OK".to_string()
+        } else if let Some(rest) = packet
+            .strip_prefix("z0,")
+            .or_else(|| packet.strip_prefix("z1,"))
+        {
+            let address = Self::parse_breakpoint_address(rest)?;
+            core_handle.clear_breakpoint(address).ok();
+            "OK".to_string()
+        } else if packet == "c" {
+            core_handle.core.run().map_err(DebuggerError::ProbeRs)?;
+            let status = self.poll_until_halted(&mut core_handle)?;
+            Self::stop_reply(status)
+        } else if packet == "s" {
+            core_handle.core.step().map_err(DebuggerError::ProbeRs)?;
+            Self::stop_reply(core_handle.core.status().map_err(DebuggerError::ProbeRs)?)
+        } else if packet.starts_with("qSupported") {
+            "PacketSize=4000;qXfer:features:read+".to_string()
+        } else if packet.starts_with("qXfer:features:read:target.xml:") {
+            self.target_description_xfer(&core_handle, session_config)
+        } else {
+            // Unrecognized/unsupported packet: respond with the empty packet, as the protocol requires.
+            String::new()
+        };
+
+        Ok(response)
+    }
+
+    /// Answer a `qXfer:features:read:target.xml:OFFSET,LENGTH` request with a target description
+    /// document, built from the core's register file (in the same order the `g` packet reports
+    /// them in) and the configured target name. The whole document is always sent as a single
+    /// final (`l`) reply; OFFSET and LENGTH are not used. Without this, a real GDB client has
+    /// no way to learn the register layout needed to decode `g`'s raw blob.
+    fn target_description_xfer(
+        &self,
+        core_handle: &super::core_data::CoreHandle,
+        _session_config: &SessionConfig,
+    ) -> String {
+        let mut registers_xml = String::new();
+        for (register_number, register) in core_handle.core.registers().registers().enumerate() {
+            registers_xml.push_str(&format!(
+                "<reg name=\"{}\" bitsize=\"32\" regnum=\"{}\" type=\"int\"/>",
+                register.name(),
+                register_number
+            ));
+        }
+
+        let target_xml = format!(
+            "<?xml version=\"1.0\"?><target version=\"1.0\"><architecture>{}</architecture><feature name=\"org.gnu.gdb.arm.core\">{registers_xml}</feature></target>",
+            core_handle.core_data.target_name
+        );
+        format!("l{target_xml}")
+    }
+
+    /// Poll the core until it reports [`CoreStatus::Halted`], mirroring the poll loop in
+    /// [`SessionData::poll_cores`], so that `c` produces a stop-reply once the target actually
+    /// halts. Also watches the client socket for GDB's async Ctrl-C interrupt — a raw `0x03` byte
+    /// sent outside of normal packet framing — and halts the core on our own initiative if it
+    /// arrives, the same way a real `gdbserver` would honor an interactive break.
+    fn poll_until_halted(
+        &mut self,
+        core_handle: &mut super::core_data::CoreHandle,
+    ) -> Result<CoreStatus, DebuggerError> {
+        self.stream
+            .set_read_timeout(Some(std::time::Duration::from_millis(50)))
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+
+        let result = loop {
+            let mut byte = [0u8; 1];
+            match self.stream.read(&mut byte) {
+                Ok(1) if byte[0] == 0x03 => {
+                    core_handle
+                        .core
+                        .halt(std::time::Duration::from_millis(500))
+                        .map_err(DebuggerError::ProbeRs)?;
+                    break core_handle.core.status().map_err(DebuggerError::ProbeRs);
+                }
+                Ok(0) => {
+                    // The client disconnected mid-`continue`. Unlike a timeout, a closed socket
+                    // keeps returning `Ok(0)` immediately on every subsequent read, so treating it
+                    // like "no byte yet" would spin this loop at full speed. Halt the core (so we
+                    // don't leave it running unattended) and bail out of the poll.
+                    core_handle
+                        .core
+                        .halt(std::time::Duration::from_millis(500))
+                        .map_err(DebuggerError::ProbeRs)?;
+                    break core_handle.core.status().map_err(DebuggerError::ProbeRs);
+                }
+                Ok(_) => {}
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(error) => break Err(DebuggerError::Other(anyhow!(error))),
+            }
+
+            match core_handle.core.status().map_err(DebuggerError::ProbeRs) {
+                Ok(status) if matches!(status, CoreStatus::Halted(_)) => break Ok(status),
+                Ok(_) => {}
+                Err(error) => break Err(error),
+            }
+        };
+
+        // Restore blocking reads for normal packet handling once we're done polling.
+        self.stream
+            .set_read_timeout(None)
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+
+        result
+    }
+
+    /// Translate a [`CoreStatus`] into the GDB RSP stop-reply packet clients expect, so that
+    /// breakpoint hits, single-step completion, and signals all render correctly.
+    fn stop_reply(status: CoreStatus) -> String {
+        match status {
+            CoreStatus::Halted(HaltReason::Breakpoint(_)) => "T05swbreak:;".to_string(),
+            CoreStatus::Halted(HaltReason::Step) => "T05;".to_string(),
+            CoreStatus::Halted(_) => "T05;".to_string(),
+            CoreStatus::Running | CoreStatus::Sleeping | CoreStatus::Unknown => "S00".to_string(),
+        }
+    }
+
+    fn parse_address_length(value: &str) -> Result<(u64, u64), DebuggerError> {
+        let (address, length) = value
+            .split_once(',')
+            .ok_or_else(|| DebuggerError::Other(anyhow!("Malformed address,length pair: `{value}`")))?;
+        let address = u64::from_str_radix(address, 16)
+            .map_err(|_| DebuggerError::Other(anyhow!("Invalid address: `{address}`")))?;
+        let length = u64::from_str_radix(length, 16)
+            .map_err(|_| DebuggerError::Other(anyhow!("Invalid length: `{length}`")))?;
+        Ok((address, length))
+    }
+
+    fn parse_breakpoint_address(value: &str) -> Result<u64, DebuggerError> {
+        let address = value
+            .split(',')
+            .next()
+            .ok_or_else(|| DebuggerError::Other(anyhow!("Malformed breakpoint packet: `{value}`")))?;
+        u64::from_str_radix(address, 16)
+            .map_err(|_| DebuggerError::Other(anyhow!("Invalid breakpoint address: `{address}`")))
+    }
+
+    /// Read one `$packet-data#checksum` frame, stripping the framing and acknowledging it.
+    fn read_packet(&mut self) -> Result<Option<String>, DebuggerError> {
+        let mut byte = [0u8; 1];
+        loop {
+            if self
+                .stream
+                .read(&mut byte)
+                .map_err(|error| DebuggerError::Other(anyhow!(error)))?
+                == 0
+            {
+                return Ok(None);
+            }
+            if byte[0] == b'$' {
+                break;
+            }
+        }
+
+        let mut packet = Vec::new();
+        loop {
+            if self
+                .stream
+                .read(&mut byte)
+                .map_err(|error| DebuggerError::Other(anyhow!(error)))?
+                == 0
+            {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            packet.push(byte[0]);
+        }
+        // Consume (and ignore) the two-digit checksum that follows.
+        let mut checksum = [0u8; 2];
+        self.stream
+            .read_exact(&mut checksum)
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+
+        self.stream
+            .write_all(b"+")
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+
+        Ok(Some(String::from_utf8_lossy(&packet).into_owned()))
+    }
+
+    /// Frame `payload` as `$payload#checksum` and send it.
+    fn write_packet(&mut self, payload: &str) -> Result<(), DebuggerError> {
+        let checksum = payload
+            .bytes()
+            .fold(0u8, |checksum, byte| checksum.wrapping_add(byte));
+        let framed = format!("${payload}#{checksum:02x}");
+        self.stream
+            .write_all(framed.as_bytes())
+            .map_err(|error| DebuggerError::Other(anyhow!(error)))
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_encode_formats_bytes_as_lowercase_hex() {
+        assert_eq!(hex_encode(&[0x00, 0xab, 0xff]), "00abff");
+        assert_eq!(hex_encode(&[]), "");
+    }
+
+    #[test]
+    fn hex_decode_parses_hex_pairs_into_bytes() {
+        assert_eq!(hex_decode("00abff").unwrap(), vec![0x00, 0xab, 0xff]);
+        assert_eq!(hex_decode("").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn hex_decode_rejects_odd_length_input() {
+        assert!(hex_decode("abc").is_err());
+    }
+
+    #[test]
+    fn hex_decode_rejects_invalid_hex_digits() {
+        assert!(hex_decode("zz").is_err());
+    }
+
+    #[test]
+    fn parse_address_length_splits_on_comma() {
+        let (address, length) = GdbConnection::parse_address_length("1000,4").unwrap();
+        assert_eq!(address, 0x1000);
+        assert_eq!(length, 0x4);
+    }
+
+    #[test]
+    fn parse_address_length_rejects_missing_comma() {
+        assert!(GdbConnection::parse_address_length("1000").is_err());
+    }
+
+    #[test]
+    fn parse_breakpoint_address_ignores_trailing_kind() {
+        let address = GdbConnection::parse_breakpoint_address("2000,1").unwrap();
+        assert_eq!(address, 0x2000);
+    }
+
+    #[test]
+    fn stop_reply_encodes_step_and_running() {
+        assert_eq!(
+            GdbConnection::stop_reply(CoreStatus::Halted(HaltReason::Step)),
+            "T05;"
+        );
+        assert_eq!(GdbConnection::stop_reply(CoreStatus::Running), "S00");
+        assert_eq!(GdbConnection::stop_reply(CoreStatus::Sleeping), "S00");
+    }
+}
+
+fn hex_decode(value: &str) -> Result<Vec<u8>, DebuggerError> {
+    if value.len() % 2 != 0 {
+        return Err(DebuggerError::Other(anyhow!(
+            "Malformed 'M' packet data (odd-length hex): `{value}`"
+        )));
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|index| {
+            u8::from_str_radix(&value[index..index + 2], 16)
+                .map_err(|_| DebuggerError::Other(anyhow!("Invalid hex data: `{value}`")))
+        })
+        .collect()
+}
@@ -0,0 +1,25 @@
+pub(crate) mod configuration;
+pub(crate) mod core_data;
+pub(crate) mod debug_rtt;
+pub(crate) mod gdb;
+pub(crate) mod session_data;
+
+use self::{
+    configuration::SessionConfig,
+    gdb::GdbServer,
+    session_data::SessionData,
+};
+use crate::DebuggerError;
+
+/// Serve the GDB Remote Serial Protocol on `gdb_port`, using a freshly-created [`SessionData`] as
+/// the execution backend. This is the GDB-client counterpart to the DAP adapter's request loop:
+/// where the DAP loop drives a [`SessionData`] from `DebugAdapter` requests, this drives the same
+/// [`SessionData`]/[`core_data::CoreHandle`] machinery from GDB Remote Serial Protocol packets, so
+/// `gdb`, `lldb`, and editor GDB integrations can debug alongside (or instead of) the DAP path.
+pub fn run_gdb_server(session_config: &SessionConfig, gdb_port: u16) -> Result<(), DebuggerError> {
+    let mut session_data = SessionData::new(session_config)?;
+    let mut gdb_server = GdbServer::new(gdb_port)?;
+    loop {
+        gdb_server.run(&mut session_data, session_config)?;
+    }
+}
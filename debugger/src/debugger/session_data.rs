@@ -25,6 +25,11 @@ pub enum BreakpointType {
 pub struct ActiveBreakpoint {
     pub(crate) breakpoint_type: BreakpointType,
     pub(crate) breakpoint_address: u64,
+    /// The symbol this breakpoint was resolved from, if it was set via
+    /// [`super::core_data::CoreHandle::set_breakpoint_at_symbol`]. Only recorded here:
+    /// [`super::core_data::CoreHandle::clear_breakpoints`] matches breakpoints by type and
+    /// address, not by this name.
+    pub(crate) source_symbol: Option<String>,
 }
 
 /// SessionData is designed to be similar to [probe_rs::Session], in as much that it provides handles to the [CoreHandle] instances for each of the available [probe_rs::Core] involved in the debug session.
@@ -175,6 +180,9 @@ impl SessionData {
                 stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
                 breakpoints: Vec::<ActiveBreakpoint>::new(),
                 rtt_connection: None,
+                rtt_setup_breakpoint: None,
+                stack_canary: None,
+                has_painted_stack_canary: false,
             })
         }
 
@@ -231,10 +239,46 @@ impl SessionData {
             if let Ok(mut target_core) = self.attach_core(core_config.core_index) {
                 match target_core.core.status() {
                     Ok(new_status) => {
+                        // Stack-overflow canary: we can't rely on the target's RAM being
+                        // reinitialized when `connect_under_reset` is used, so leave it disabled
+                        // in that case rather than risk painting over whatever is already there.
+                        if !session_config.connect_under_reset {
+                            if matches!(new_status, CoreStatus::Running)
+                                && !target_core.core_data.has_painted_stack_canary
+                            {
+                                target_core
+                                    .paint_stack_canary(core_config.stack_canary_budget)
+                                    .ok();
+                                target_core.core_data.has_painted_stack_canary = true;
+                            } else if matches!(new_status, CoreStatus::Halted(_))
+                                && new_status != debug_adapter.last_known_status
+                            {
+                                match target_core.check_stack_canary() {
+                                    Ok(Some(usage_percent)) => {
+                                        debug_adapter.log_to_console(format!(
+                                            "Maximum stack usage: {usage_percent:.1}%"
+                                        ));
+                                    }
+                                    Ok(None) => {}
+                                    Err(error) => {
+                                        debug_adapter.send_error_response(&error).ok();
+                                    }
+                                }
+                            }
+                        }
+
                         // If appropriate, check for RTT data.
+                        //
+                        // While a core is parked at the RTT setup breakpoint (`rtt_setup_breakpoint`
+                        // is `Some`), it stays `Halted` with no further status transition, so the
+                        // `new_status != last_known_status` check alone would only let us retry
+                        // `attach_to_rtt` once: a single transient attach failure would otherwise
+                        // stall RTT for the rest of the session. Keep polling on every tick while a
+                        // setup breakpoint is pending, regardless of status transitions.
                         if core_config.rtt_config.enabled
                             && ((matches!(new_status, CoreStatus::Halted(_))
-                                && new_status != debug_adapter.last_known_status)
+                                && (new_status != debug_adapter.last_known_status
+                                    || target_core.core_data.rtt_setup_breakpoint.is_some()))
                                 || !matches!(new_status, CoreStatus::Halted(_)))
                         {
                             if let Some(core_rtt) = &mut target_core.core_data.rtt_connection {
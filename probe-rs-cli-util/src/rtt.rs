@@ -0,0 +1,219 @@
+//! Thin wrapper around [SEGGER RTT](https://www.segger.com/products/debug-probes/j-link/technology/about-real-time-transfer/)
+//! control-block discovery and channel I/O, shared by the various probe-rs CLI front-ends.
+
+use anyhow::{anyhow, Result};
+use probe_rs::{config::MemoryRegion, Core};
+use std::ops::Range;
+use std::path::Path;
+
+/// The magic bytes ("SEGGER RTT") that mark the start of an RTT control block in target memory.
+const RTT_ID: &[u8] = b"SEGGER RTT\0\0\0\0\0\0";
+
+/// How an RTT channel's data should be interpreted when it is read from the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    String,
+    BinaryLE,
+    Defmt,
+}
+
+/// What a channel should do when the host isn't reading fast enough and its buffer fills up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelMode {
+    NoBlockSkip,
+    NoBlockTrim,
+    BlockIfFull,
+}
+
+/// Per-channel configuration, matched against the channel names reported by the target's control
+/// block.
+#[derive(Clone, Debug)]
+pub struct RttChannelConfig {
+    pub channel_name: Option<String>,
+    pub data_format: DataFormat,
+}
+
+/// Configuration for locating and attaching to a target's RTT control block.
+#[derive(Clone, Debug, Default)]
+pub struct RttConfig {
+    pub enabled: bool,
+    pub channels: Vec<RttChannelConfig>,
+    /// Skip scanning entirely and validate the control block at this address.
+    pub control_block_address: Option<u64>,
+    /// Resolve the control-block symbol via `CoreData::debug_info` before attaching, when a raw
+    /// address is not known ahead of time.
+    pub control_block_symbol: Option<String>,
+    /// Constrain the control-block scan to this address range, resolved from a named memory
+    /// region when `scan_region_name` is set.
+    pub scan_region: Option<Range<u64>>,
+    /// The name of a RAM `MemoryRegion` (as reported by the target description) to scan,
+    /// resolved to `scan_region` before attaching. Other kinds of region are not matched.
+    pub scan_region_name: Option<String>,
+    /// Defer attaching until the core halts at this address or symbol. See
+    /// `CoreHandle::attach_to_rtt` for how this gates the initial scan.
+    pub setup_on_breakpoint: Option<String>,
+    /// Whether `setup_on_breakpoint`'s resolved address should have its thumb bit (LSB) cleared.
+    pub thumb: bool,
+}
+
+/// A single active up-channel, resolved from the target's control block.
+pub struct UpChannel {
+    number: usize,
+    buffer_address: u64,
+    size: u32,
+    write_offset_address: u64,
+    read_offset_address: u64,
+    flags_address: u64,
+}
+
+impl UpChannel {
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Set this channel's [`ChannelMode`] (the low two bits of its flags word).
+    pub fn set_mode(&self, core: &mut Core, mode: ChannelMode) -> Result<()> {
+        let mut flags = core.read_word_32(self.flags_address)?;
+        flags &= !0b11;
+        flags |= match mode {
+            ChannelMode::NoBlockSkip => 0b00,
+            ChannelMode::NoBlockTrim => 0b01,
+            ChannelMode::BlockIfFull => 0b10,
+        };
+        core.write_word_32(self.flags_address, flags)?;
+        Ok(())
+    }
+
+    /// Read as many bytes as are currently available into `buffer`, without blocking.
+    pub fn read(&mut self, core: &mut Core, buffer: &mut [u8]) -> Result<usize> {
+        let write_offset = core.read_word_32(self.write_offset_address)? as u64;
+        let mut read_offset = core.read_word_32(self.read_offset_address)? as u64;
+
+        let available = if write_offset >= read_offset {
+            write_offset - read_offset
+        } else {
+            u64::from(self.size) - read_offset + write_offset
+        };
+        let to_read = available.min(buffer.len() as u64) as usize;
+
+        for byte in buffer.iter_mut().take(to_read) {
+            let mut word = [0u8; 1];
+            core.read_8(self.buffer_address + read_offset, &mut word)?;
+            *byte = word[0];
+            read_offset = (read_offset + 1) % u64::from(self.size);
+        }
+        core.write_word_32(self.read_offset_address, read_offset as u32)?;
+
+        Ok(to_read)
+    }
+}
+
+/// A single active channel (its up-channel half, if the control block has one for this slot) plus
+/// the [`RttChannelConfig`] it was matched against.
+pub struct RttActiveChannel {
+    pub up_channel: Option<UpChannel>,
+    pub channel_name: String,
+    pub data_format: DataFormat,
+}
+
+/// The result of successfully attaching to a target's RTT control block.
+pub struct RttActiveTarget {
+    pub active_channels: Vec<RttActiveChannel>,
+}
+
+/// Attach to the target's RTT control block, per `rtt_config`: validating an explicit
+/// `control_block_address` if one was resolved, otherwise scanning `target_memory_map` (or just
+/// `rtt_config.scan_region`, if set) for the `SEGGER RTT` magic bytes.
+pub fn attach_to_rtt(
+    core: &mut Core,
+    target_memory_map: &[MemoryRegion],
+    _program_binary: &Path,
+    rtt_config: &RttConfig,
+) -> Result<RttActiveTarget> {
+    let control_block_address = match rtt_config.control_block_address {
+        Some(address) => address,
+        None => find_control_block(core, target_memory_map, rtt_config.scan_region.as_ref())?,
+    };
+
+    let mut header = [0u8; RTT_ID.len()];
+    core.read_8(control_block_address, &mut header)?;
+    if header != RTT_ID {
+        return Err(anyhow!(
+            "No RTT control block found at {:#010x}",
+            control_block_address
+        ));
+    }
+
+    let max_up_channels = core.read_word_32(control_block_address + 16)?;
+    let channel_descriptor_size = 6 * 4; // name, buffer, size, write offset, read offset, flags.
+    let channels_base = control_block_address + 24;
+
+    let mut active_channels = Vec::new();
+    for channel_index in 0..max_up_channels {
+        let descriptor_address = channels_base + u64::from(channel_index) * channel_descriptor_size;
+        let buffer_address = u64::from(core.read_word_32(descriptor_address + 4)?);
+        if buffer_address == 0 {
+            continue;
+        }
+        let size = core.read_word_32(descriptor_address + 8)?;
+
+        let requested = rtt_config
+            .channels
+            .get(channel_index as usize)
+            .cloned()
+            .unwrap_or(RttChannelConfig {
+                channel_name: None,
+                data_format: DataFormat::String,
+            });
+
+        active_channels.push(RttActiveChannel {
+            up_channel: Some(UpChannel {
+                number: channel_index as usize,
+                buffer_address,
+                size,
+                write_offset_address: descriptor_address + 12,
+                read_offset_address: descriptor_address + 16,
+                flags_address: descriptor_address + 20,
+            }),
+            channel_name: requested
+                .channel_name
+                .unwrap_or_else(|| format!("Channel {channel_index}")),
+            data_format: requested.data_format,
+        });
+    }
+
+    Ok(RttActiveTarget { active_channels })
+}
+
+/// Scan `region` (or, if `None`, every RAM region in `target_memory_map`) for the RTT control
+/// block's magic bytes.
+fn find_control_block(
+    core: &mut Core,
+    target_memory_map: &[MemoryRegion],
+    region: Option<&Range<u64>>,
+) -> Result<u64> {
+    let ranges: Vec<Range<u64>> = match region {
+        Some(region) => vec![region.clone()],
+        None => target_memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Ram(ram) => Some(ram.range.clone()),
+                _ => None,
+            })
+            .collect(),
+    };
+
+    for range in ranges {
+        let mut address = range.start;
+        let mut window = vec![0u8; RTT_ID.len()];
+        while address + RTT_ID.len() as u64 <= range.end {
+            core.read_8(address, &mut window)?;
+            if window == RTT_ID {
+                return Ok(address);
+            }
+            address += 4;
+        }
+    }
+
+    Err(anyhow!("No RTT control block found in the scanned memory"))
+}